@@ -0,0 +1,190 @@
+use core::time::Duration;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Where the breaker currently sits. See `CircuitBreaker` for the transition rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Whether the single `HalfOpen` probe request is currently outstanding.
+    /// Guards against concurrent callers (the agent polls many in-flight
+    /// tasks at once) all slipping through during the same recovery window.
+    probe_in_flight: bool,
+}
+
+/// A classic Closed/Open/HalfOpen circuit breaker guarding the Snarkify HTTP client.
+///
+/// While `Open`, calls are rejected immediately instead of paying the full
+/// `send_timeout` + retry backoff, so a down backend doesn't stall the agent's
+/// polling loop. Only transport failures and 5xx responses count against the
+/// breaker; 4xx responses mean the request itself was bad and shouldn't trip it.
+pub struct CircuitBreaker {
+    inner: Mutex<Inner>,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+            failure_threshold,
+            cooldown,
+        }
+    }
+
+    /// Call before issuing a request. Returns `false` if the caller should
+    /// fail fast instead of hitting the network: either the breaker is open
+    /// and the cooldown hasn't elapsed yet, or it just moved to `HalfOpen` and
+    /// another caller already holds the single probe slot. Moves
+    /// `Open` -> `HalfOpen` itself once the cooldown has elapsed, admitting
+    /// exactly one in-flight probe request until its outcome is recorded.
+    pub fn allow_request(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => {
+                if inner.probe_in_flight {
+                    false
+                } else {
+                    inner.probe_in_flight = true;
+                    true
+                }
+            }
+            CircuitState::Open => {
+                let cooldown_elapsed = inner
+                    .opened_at
+                    .map(|t| t.elapsed() >= self.cooldown)
+                    .unwrap_or(true);
+                if cooldown_elapsed {
+                    inner.state = CircuitState::HalfOpen;
+                    inner.probe_in_flight = true;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Record a successful call: closes the breaker and resets the failure count.
+    pub fn on_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_in_flight = false;
+    }
+
+    /// Record a breaker-relevant failure (transport error or 5xx). A failure
+    /// while `HalfOpen` re-opens immediately; a failure while `Closed` opens
+    /// once `failure_threshold` consecutive failures have been seen.
+    pub fn on_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            CircuitState::HalfOpen => {
+                inner.state = CircuitState::Open;
+                inner.opened_at = Some(Instant::now());
+                inner.probe_in_flight = false;
+            }
+            CircuitState::Closed | CircuitState::Open => {
+                inner.consecutive_failures += 1;
+                if inner.consecutive_failures >= self.failure_threshold {
+                    inner.state = CircuitState::Open;
+                    inner.opened_at = Some(Instant::now());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_allows_requests_below_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        assert!(breaker.allow_request());
+        breaker.on_failure();
+        assert!(breaker.allow_request());
+        breaker.on_failure();
+        // Still below the threshold of 3 consecutive failures.
+        assert!(breaker.allow_request());
+    }
+
+    #[test]
+    fn opens_after_consecutive_failure_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        breaker.on_failure();
+        breaker.on_failure();
+        breaker.on_failure();
+        assert!(!breaker.allow_request());
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        breaker.on_failure();
+        breaker.on_failure();
+        breaker.on_success();
+        breaker.on_failure();
+        breaker.on_failure();
+        // Two failures since the reset, still below the threshold of 3.
+        assert!(breaker.allow_request());
+    }
+
+    #[test]
+    fn stays_open_until_cooldown_elapses() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+        breaker.on_failure();
+        assert!(!breaker.allow_request());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow_request());
+    }
+
+    #[test]
+    fn half_open_admits_only_one_probe_concurrently() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+        breaker.on_failure();
+        std::thread::sleep(Duration::from_millis(15));
+        // First caller gets the probe slot, a concurrent second caller does not.
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+    }
+
+    #[test]
+    fn half_open_success_closes_the_breaker() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+        breaker.on_failure();
+        std::thread::sleep(Duration::from_millis(15));
+        assert!(breaker.allow_request());
+        breaker.on_success();
+        // Closed again: any number of callers can proceed.
+        assert!(breaker.allow_request());
+        assert!(breaker.allow_request());
+    }
+
+    #[test]
+    fn half_open_failure_reopens_the_breaker() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(10));
+        breaker.on_failure();
+        std::thread::sleep(Duration::from_millis(15));
+        assert!(breaker.allow_request());
+        breaker.on_failure();
+        assert!(!breaker.allow_request());
+    }
+}
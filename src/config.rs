@@ -0,0 +1,61 @@
+use scroll_proving_sdk::config::CloudProverConfig;
+use serde::Deserialize;
+
+/// Consecutive breaker-relevant failures (transport errors / 5xx) before the
+/// circuit opens, used when a config omits `circuit_breaker_failure_threshold`.
+const DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD: u32 = 5;
+
+/// How long the breaker stays open before allowing a single probe request,
+/// used when a config omits `circuit_breaker_cooldown_sec`.
+const DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SEC: u64 = 30;
+
+fn default_circuit_breaker_failure_threshold() -> u32 {
+    DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD
+}
+
+fn default_circuit_breaker_cooldown_sec() -> u64 {
+    DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SEC
+}
+
+/// Snarkify-specific prover configuration. Wraps the SDK's generic
+/// `CloudProverConfig` with the settings this prover needs that aren't part
+/// of the shared SDK config, so operators can tune them from the same config
+/// file/struct they already use for `base_url`, `retry_count`, etc.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnarkifyProverConfig {
+    #[serde(flatten)]
+    pub cloud: CloudProverConfig,
+
+    /// Consecutive breaker-relevant failures before the circuit opens.
+    #[serde(default = "default_circuit_breaker_failure_threshold")]
+    pub circuit_breaker_failure_threshold: u32,
+
+    /// Seconds the breaker stays open before allowing a single probe request.
+    #[serde(default = "default_circuit_breaker_cooldown_sec")]
+    pub circuit_breaker_cooldown_sec: u64,
+
+    /// Extra trusted root CA PEM paths, on top of the platform trust store.
+    #[serde(default)]
+    pub extra_root_ca_paths: Vec<String>,
+
+    /// Client certificate PEM path, for reaching mTLS-gated endpoints.
+    #[serde(default)]
+    pub client_cert_path: Option<String>,
+
+    /// Client private key PEM path, for reaching mTLS-gated endpoints.
+    #[serde(default)]
+    pub client_key_path: Option<String>,
+}
+
+impl From<CloudProverConfig> for SnarkifyProverConfig {
+    fn from(cloud: CloudProverConfig) -> Self {
+        Self {
+            cloud,
+            circuit_breaker_failure_threshold: DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD,
+            circuit_breaker_cooldown_sec: DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SEC,
+            extra_root_ca_paths: Vec::new(),
+            client_cert_path: None,
+            client_key_path: None,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use core::time::Duration;
+use reqwest::StatusCode;
+
+/// Errors produced while talking to the Snarkify HTTP API.
+///
+/// Kept as a typed enum (rather than `anyhow::Error`) so that callers such as
+/// the polling/retry loop in the agent can branch on error *class* instead of
+/// matching on formatted strings.
+#[derive(Debug, thiserror::Error)]
+pub enum SnarkifyError {
+    /// The API key was missing or rejected (HTTP 401).
+    #[error("unauthorized: check the configured Snarkify API key")]
+    Unauthorized,
+
+    /// Snarkify is throttling us (HTTP 429), optionally telling us how long to back off.
+    #[error("rate limited by Snarkify{}", .retry_after.map(|d| format!(", retry after {:?}", d)).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// Snarkify returned a 5xx; the failure is on their side.
+    #[error("Snarkify server error: {0}")]
+    ServerError(StatusCode),
+
+    /// Any other non-2xx/3xx status we don't special-case above.
+    #[error("unexpected status from Snarkify: {0}")]
+    BadStatus(StatusCode),
+
+    /// Request failed below the HTTP layer (connection refused, timeout, TLS, etc.)
+    /// or one of the retry/middleware layers gave up.
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest_middleware::Error),
+
+    /// The connection succeeded but reading the response body failed partway
+    /// through (dropped connection, body too large, etc.).
+    #[error("failed to read response body: {0}")]
+    BodyRead(#[from] reqwest::Error),
+
+    /// The response body wasn't valid JSON, or didn't match the expected shape.
+    #[error("failed to deserialize response: {0}")]
+    Deserialize(#[from] serde_json::Error),
+
+    /// The configured base URL plus method path didn't form a valid URL.
+    #[error("failed to parse URL '{0}': {1}")]
+    UrlParse(String, #[source] url::ParseError),
+
+    /// The circuit breaker is open (Snarkify has been failing repeatedly) and
+    /// the call was rejected before touching the network.
+    #[error("circuit breaker open, failing fast instead of calling Snarkify")]
+    CircuitOpen,
+
+    /// Building the TLS client config failed: an unreadable/malformed PEM
+    /// file, or the rustls backend itself rejected the configured material.
+    #[error("TLS configuration error: {0}")]
+    Tls(String),
+}
+
+impl SnarkifyError {
+    /// Maps an HTTP response status (plus an optional `Retry-After` header value,
+    /// already extracted by the caller) to the matching error variant.
+    pub fn from_status(status: StatusCode, retry_after: Option<Duration>) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => SnarkifyError::Unauthorized,
+            StatusCode::TOO_MANY_REQUESTS => SnarkifyError::RateLimited { retry_after },
+            s if s.is_server_error() => SnarkifyError::ServerError(s),
+            s => SnarkifyError::BadStatus(s),
+        }
+    }
+
+    /// Whether this failure originated from Snarkify being down/overloaded rather
+    /// than from something being wrong with the request itself.
+    pub fn is_server_side(&self) -> bool {
+        matches!(
+            self,
+            SnarkifyError::ServerError(_) | SnarkifyError::Transport(_)
+        )
+    }
+
+    /// Stable, `snake_case` discriminant for this variant, independent of the
+    /// human-readable message. Callers that only see the stringified
+    /// `ProveResponse::error`/`QueryTaskResponse::error` can still match on this
+    /// prefix instead of parsing the rest of the message.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SnarkifyError::Unauthorized => "unauthorized",
+            SnarkifyError::RateLimited { .. } => "rate_limited",
+            SnarkifyError::ServerError(_) => "server_error",
+            SnarkifyError::BadStatus(_) => "bad_status",
+            SnarkifyError::Transport(_) => "transport",
+            SnarkifyError::BodyRead(_) => "body_read",
+            SnarkifyError::Deserialize(_) => "deserialize",
+            SnarkifyError::UrlParse(..) => "url_parse",
+            SnarkifyError::CircuitOpen => "circuit_open",
+            SnarkifyError::Tls(_) => "tls",
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use crate::circuit_breaker::CircuitBreaker;
+use crate::config::SnarkifyProverConfig;
+use crate::error::SnarkifyError;
+use crate::tls::TlsConfig;
 use crate::types::{
     SnarkifyCreateTaskInput, SnarkifyCreateTaskRequest, SnarkifyGetTaskResponse,
     SnarkifyGetVkResponse,
@@ -5,21 +9,23 @@ use crate::types::{
 use async_trait::async_trait;
 use core::time::Duration;
 use log::error;
-use reqwest::{header::CONTENT_TYPE, Url};
+use reqwest::{
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    Url,
+};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
-use scroll_proving_sdk::{
-    config::CloudProverConfig,
-    prover::{
-        proving_service::{
-            GetVkRequest, GetVkResponse, ProveRequest, ProveResponse, QueryTaskRequest,
-            QueryTaskResponse, TaskStatus,
-        },
-        types::CircuitType,
-        ProvingService,
+use scroll_proving_sdk::prover::{
+    proving_service::{
+        GetVkRequest, GetVkResponse, ProveRequest, ProveResponse, QueryTaskRequest,
+        QueryTaskResponse, TaskStatus,
     },
+    types::CircuitType,
+    ProvingService,
 };
 use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 
 /// API version used by the Snarkify platform.
 const API_VERSION: &str = "v1";
@@ -30,6 +36,7 @@ pub struct SnarkifyProver {
     service_id: String,
     send_timeout: Duration,
     client: ClientWithMiddleware,
+    circuit_breaker: Arc<CircuitBreaker>,
 }
 
 #[async_trait]
@@ -54,40 +61,14 @@ impl ProvingService for SnarkifyProver {
                 error!("get_vk method failed: {:?}", e);
                 GetVkResponse {
                     vk: String::new(),
-                    error: Some(format!("Failed to get vk: {}", e)),
+                    error: Some(format!("[{}] Failed to get vk: {}", e.kind(), e)),
                 }
             }
         }
     }
 
     async fn prove(&self, req: ProveRequest) -> ProveResponse {
-        let body = SnarkifyCreateTaskRequest::from_prove_request(&req);
-        let method = format!("/{}/services/{}", API_VERSION, &self.service_id);
-
-        match self
-            .post_with_token::<SnarkifyCreateTaskRequest, SnarkifyGetTaskResponse>(&method, &body)
-            .await
-        {
-            Ok(resp) => ProveResponse {
-                task_id: resp.task_id,
-                circuit_type: req.circuit_type,
-                circuit_version: req.circuit_version,
-                hard_fork_name: req.hard_fork_name,
-                status: resp.state.into(),
-                created_at: resp.created.map(|t| t.timestamp() as f64).unwrap_or(0.0),
-                started_at: resp.started.map(|t| t.timestamp() as f64),
-                finished_at: None,
-                compute_time_sec: None,
-                input: Some(req.input.clone()),
-                proof: None,
-                vk: None,
-                error: None,
-            },
-            Err(e) => {
-                error!("prove method failed: {:?}", e);
-                self.build_prove_error_response(&req, &format!("Failed to request proof: {}", e))
-            }
-        }
+        self.prove_with_idempotency_key(req, None).await
     }
 
     async fn query_task(&self, req: QueryTaskRequest) -> QueryTaskResponse {
@@ -130,28 +111,120 @@ impl ProvingService for SnarkifyProver {
             }
             Err(e) => {
                 error!("query_task method failed: {:?}", e);
-                self.build_query_task_error_response(&req, &format!("Failed to query proof: {}", e))
+                self.build_query_task_error_response(
+                    &req,
+                    &format!("[{}] Failed to query proof: {}", e.kind(), e),
+                )
             }
         }
     }
 }
 
 impl SnarkifyProver {
-    pub fn new(cfg: CloudProverConfig, service_id: String) -> Self {
-        let retry_wait_duration = Duration::from_secs(cfg.retry_wait_time_sec);
+    /// Builds a prover, deriving its TLS trust store and client identity from
+    /// `cfg`'s `extra_root_ca_paths`/`client_cert_path`/`client_key_path`
+    /// fields. Use [`SnarkifyProver::new_with_tls`] directly to pass a
+    /// hand-built `TlsConfig` instead. Accepts either a bare
+    /// `CloudProverConfig` (breaker thresholds and TLS material default to
+    /// [`crate::config::SnarkifyProverConfig`]'s defaults) or a
+    /// `SnarkifyProverConfig` when the operator wants to tune them.
+    pub fn new(
+        cfg: impl Into<SnarkifyProverConfig>,
+        service_id: String,
+    ) -> Result<Self, SnarkifyError> {
+        let cfg = cfg.into();
+        let tls = TlsConfig {
+            extra_root_ca_paths: cfg.extra_root_ca_paths.clone(),
+            client_cert_path: cfg.client_cert_path.clone(),
+            client_key_path: cfg.client_key_path.clone(),
+        };
+        Self::new_with_tls(cfg, service_id, tls)
+    }
+
+    /// Builds a prover, configuring the inner HTTP client's TLS trust store
+    /// and client identity from `tls`. Falls back to a bare
+    /// `reqwest::Client::new()` when `tls` carries no custom material, so
+    /// the common case pays no extra rustls setup cost.
+    pub fn new_with_tls(
+        cfg: impl Into<SnarkifyProverConfig>,
+        service_id: String,
+        tls: TlsConfig,
+    ) -> Result<Self, SnarkifyError> {
+        let cfg = cfg.into();
+        let retry_wait_duration = Duration::from_secs(cfg.cloud.retry_wait_time_sec);
         let retry_policy = ExponentialBackoff::builder()
             .retry_bounds(retry_wait_duration / 2, retry_wait_duration)
-            .build_with_max_retries(cfg.retry_count);
-        let client = ClientBuilder::new(reqwest::Client::new())
+            .build_with_max_retries(cfg.cloud.retry_count);
+
+        let inner_client = if tls.is_default() {
+            reqwest::Client::new()
+        } else {
+            let rustls_config = tls.build_rustls_config()?;
+            reqwest::Client::builder()
+                .use_preconfigured_tls(rustls_config)
+                .build()
+                .map_err(|e| SnarkifyError::Tls(e.to_string()))?
+        };
+        let client = ClientBuilder::new(inner_client)
             .with(RetryTransientMiddleware::new_with_policy(retry_policy))
             .build();
 
-        Self {
-            base_url: cfg.base_url,
-            api_key: cfg.api_key,
+        Ok(Self {
+            base_url: cfg.cloud.base_url,
+            api_key: cfg.cloud.api_key,
             service_id,
-            send_timeout: Duration::from_secs(cfg.connection_timeout_sec),
+            send_timeout: Duration::from_secs(cfg.cloud.connection_timeout_sec),
             client,
+            circuit_breaker: Arc::new(CircuitBreaker::new(
+                cfg.circuit_breaker_failure_threshold,
+                Duration::from_secs(cfg.circuit_breaker_cooldown_sec),
+            )),
+        })
+    }
+
+    /// Same as `prove`, but callers that already know the logical task's
+    /// idempotency key (e.g. retrying a call whose response got lost, for a
+    /// task UUID they already minted) can supply it directly instead of
+    /// letting one be derived from the request fields.
+    pub async fn prove_with_idempotency_key(
+        &self,
+        req: ProveRequest,
+        idempotency_key: Option<String>,
+    ) -> ProveResponse {
+        let body = SnarkifyCreateTaskRequest::from_prove_request(&req);
+        let method = format!("/{}/services/{}", API_VERSION, &self.service_id);
+        let idempotency_key = idempotency_key.unwrap_or_else(|| self.idempotency_key(&req));
+
+        match self
+            .post_with_token::<SnarkifyCreateTaskRequest, SnarkifyGetTaskResponse>(
+                &method,
+                &body,
+                Some(&idempotency_key),
+            )
+            .await
+        {
+            Ok(resp) => ProveResponse {
+                task_id: resp.task_id,
+                circuit_type: req.circuit_type,
+                circuit_version: req.circuit_version,
+                hard_fork_name: req.hard_fork_name,
+                status: resp.state.into(),
+                created_at: resp.created.map(|t| t.timestamp() as f64).unwrap_or(0.0),
+                started_at: resp.started.map(|t| t.timestamp() as f64),
+                finished_at: None,
+                compute_time_sec: None,
+                input: Some(req.input.clone()),
+                proof: None,
+                vk: None,
+                error: None,
+            },
+            Err(e) => {
+                error!("prove method failed: {:?}", e);
+                self.build_prove_error_response(
+                    &req,
+                    &format!("[{}] Failed to request proof: {}", e.kind(), e),
+                )
+            }
         }
     }
 
@@ -195,69 +268,171 @@ impl SnarkifyProver {
         }
     }
 
-    fn build_url(&self, method: &str) -> anyhow::Result<Url> {
+    /// Deterministic idempotency key for a logical `prove` call, derived from
+    /// the fields that identify it. All network-level retries of the same
+    /// logical request (e.g. `RetryTransientMiddleware` retrying a dropped
+    /// connection) land on the same key, so the server can dedupe instead of
+    /// starting a second, expensive proving task.
+    fn idempotency_key(&self, req: &ProveRequest) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.service_id.as_bytes());
+        hasher.update(b":");
+        hasher.update([req.circuit_type.to_u8()]);
+        hasher.update(b":");
+        hasher.update(req.circuit_version.as_bytes());
+        hasher.update(b":");
+        hasher.update(req.hard_fork_name.as_bytes());
+        hasher.update(b":");
+        if let Ok(input_bytes) = serde_json::to_vec(&req.input) {
+            hasher.update(input_bytes);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn build_url(&self, method: &str) -> Result<Url, SnarkifyError> {
         let full_url = format!("{}{}", self.base_url, method);
-        Url::parse(&full_url)
-            .map_err(|e| anyhow::anyhow!("Failed to parse URL '{}': {}", full_url, e))
+        Url::parse(&full_url).map_err(|e| SnarkifyError::UrlParse(full_url, e))
     }
 
-    async fn get_with_token<Resp>(&self, method: &str) -> anyhow::Result<Resp>
+    async fn get_with_token<Resp>(&self, method: &str) -> Result<Resp, SnarkifyError>
     where
         Resp: serde::de::DeserializeOwned,
     {
+        if !self.circuit_breaker.allow_request() {
+            return Err(SnarkifyError::CircuitOpen);
+        }
+
         let url = self.build_url(method)?;
         log::info!("[Snarkify Client], {method}, sent request");
-        let response = self
+        let result = self
             .client
             .get(url)
             .header(CONTENT_TYPE, "application/json")
             .header("X-Api-Key", &self.api_key)
             .timeout(self.send_timeout)
             .send()
-            .await?;
+            .await
+            .map_err(SnarkifyError::from);
+        self.record_breaker_outcome(&result);
+        let response = result?;
 
         let status = response.status();
         if !(status >= http::status::StatusCode::OK && status <= http::status::StatusCode::ACCEPTED)
         {
-            anyhow::bail!("[Snarkify Client], {method}, status not ok: {}", status)
+            let err = SnarkifyError::from_status(status, retry_after(&response));
+            self.record_breaker_status(&err);
+            return Err(err);
         }
 
-        let response_body = response.text().await?;
+        let response_bytes = match response.bytes().await {
+            Ok(bytes) => {
+                self.circuit_breaker.on_success();
+                bytes
+            }
+            Err(e) => {
+                self.circuit_breaker.on_failure();
+                return Err(SnarkifyError::from(e));
+            }
+        };
 
         log::info!("[Snarkify Client], {method}, received response");
-        log::debug!("[Snarkify Client], {method}, response: {response_body}");
-        serde_json::from_str(&response_body).map_err(|e| anyhow::anyhow!(e))
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!(
+                "[Snarkify Client], {method}, response: {}",
+                String::from_utf8_lossy(&response_bytes)
+            );
+        }
+        serde_json::from_slice(&response_bytes).map_err(SnarkifyError::Deserialize)
     }
 
-    async fn post_with_token<Req, Resp>(&self, method: &str, req: &Req) -> anyhow::Result<Resp>
+    async fn post_with_token<Req, Resp>(
+        &self,
+        method: &str,
+        req: &Req,
+        idempotency_key: Option<&str>,
+    ) -> Result<Resp, SnarkifyError>
     where
         Req: ?Sized + Serialize,
         Resp: serde::de::DeserializeOwned,
     {
+        if !self.circuit_breaker.allow_request() {
+            return Err(SnarkifyError::CircuitOpen);
+        }
+
         let url = self.build_url(method)?;
-        let request_body = serde_json::to_string(req)?;
+        let request_body = serde_json::to_string(req).map_err(SnarkifyError::Deserialize)?;
         log::info!("[Snarkify Client], {method}, sent request");
         log::debug!("[Snarkify Client], {method}, request: {request_body}");
-        let response = self
+        let mut request = self
             .client
             .post(url)
             .header(CONTENT_TYPE, "application/json")
-            .header("X-Api-Key", &self.api_key)
+            .header("X-Api-Key", &self.api_key);
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+        let result = request
             .body(request_body)
             .timeout(self.send_timeout)
             .send()
-            .await?;
+            .await
+            .map_err(SnarkifyError::from);
+        self.record_breaker_outcome(&result);
+        let response = result?;
 
         let status = response.status();
         if !(status >= http::status::StatusCode::OK && status <= http::status::StatusCode::ACCEPTED)
         {
-            anyhow::bail!("[Snarkify Client], {method}, status not ok: {}", status)
+            let err = SnarkifyError::from_status(status, retry_after(&response));
+            self.record_breaker_status(&err);
+            return Err(err);
         }
 
-        let response_body = response.text().await?;
+        let response_bytes = match response.bytes().await {
+            Ok(bytes) => {
+                self.circuit_breaker.on_success();
+                bytes
+            }
+            Err(e) => {
+                self.circuit_breaker.on_failure();
+                return Err(SnarkifyError::from(e));
+            }
+        };
 
         log::info!("[Snarkify Client], {method}, received response");
-        log::debug!("[Snarkify Client], {method}, response: {response_body}");
-        serde_json::from_str(&response_body).map_err(|e| anyhow::anyhow!(e))
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!(
+                "[Snarkify Client], {method}, response: {}",
+                String::from_utf8_lossy(&response_bytes)
+            );
+        }
+        serde_json::from_slice(&response_bytes).map_err(SnarkifyError::Deserialize)
+    }
+
+    /// Feeds a transport-level send result to the breaker: connection/timeout
+    /// failures count against it, a response (of any status) does not.
+    fn record_breaker_outcome(&self, result: &Result<reqwest::Response, SnarkifyError>) {
+        match result {
+            Ok(_) => {}
+            Err(_) => self.circuit_breaker.on_failure(),
+        }
     }
+
+    /// Feeds an HTTP-status-derived error to the breaker: 5xx counts as a
+    /// failure; 4xx is a well-formed rejection from Snarkify and is left alone.
+    fn record_breaker_status(&self, err: &SnarkifyError) {
+        if err.is_server_side() {
+            self.circuit_breaker.on_failure();
+        }
+    }
+}
+
+/// Parses the `Retry-After` header (seconds form) off a response, if present.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_secs)
 }